@@ -0,0 +1,430 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+// The slice of `typing::core` that `typing::expand` actually drives: the unification
+// substitution (`TVar`/`Subst`) and the per-function `Context` used to resolve and report on
+// types while expanding them.
+
+use crate::{
+    naming::ast::{BaseType, BaseType_, TParam},
+    parser::ast::{Kind, Kind_},
+};
+use move_ir_types::location::*;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+
+//**************************************************************************************************
+// Type variables & substitution
+//**************************************************************************************************
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct TVar(pub u64);
+
+// `unfolded` memoizes each tvar's fully-reified result (nested arguments included, not just the
+// first hop); `unfolded_deps` is the reverse index `bind` uses to invalidate exactly the stale
+// entries -- cascading, since one cached entry can embed another's result. Both are `RefCell`s
+// since `unfold_type_base` only ever gets `&Subst`.
+#[derive(Clone, Debug, Default)]
+pub struct Subst {
+    bindings: HashMap<TVar, BaseType>,
+    unfolded: RefCell<HashMap<TVar, BaseType>>,
+    unfolded_deps: RefCell<HashMap<TVar, Vec<TVar>>>,
+}
+
+impl Subst {
+    pub fn empty() -> Self {
+        Self {
+            bindings: HashMap::new(),
+            unfolded: RefCell::new(HashMap::new()),
+            unfolded_deps: RefCell::new(HashMap::new()),
+        }
+    }
+
+    pub fn bind(&mut self, tvar: TVar, bt: BaseType) {
+        self.bindings.insert(tvar, bt);
+        // Invalidate only the entries that actually depended on `tvar`, directly or (since a
+        // cache entry can embed another's nested result) transitively -- not the whole cache,
+        // since `bind` also runs mid-expansion to default a numeric literal.
+        let mut unfolded = self.unfolded.borrow_mut();
+        let mut unfolded_deps = self.unfolded_deps.borrow_mut();
+        let mut stale = vec![tvar];
+        while let Some(next) = stale.pop() {
+            if let Some(dependents) = unfolded_deps.remove(&next) {
+                for dep in dependents {
+                    if unfolded.remove(&dep).is_some() {
+                        stale.push(dep);
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Follows `tvar` through `subst` to a non-variable type (or `Anything` if unbound), memoizing the
+// fully-reified result. A cycle -- a direct `Var` alias loop or a compound type that embeds the
+// tvar it's bound to, e.g. `T = Vector<T>` -- is a unification bug; the caller gets `Err` instead
+// of an infinite loop or (once `expand::base_type` reifies the result) unbounded recursion.
+pub fn unfold_type_base(subst: &Subst, bt: BaseType) -> Result<BaseType, Loc> {
+    resolve(subst, bt, &mut vec![])
+}
+
+// Does the work of `unfold_type_base`, accumulating every tvar consulted (even via a cache hit)
+// into `deps` so a caller resolving an outer tvar's nested argument can register them all as its
+// own dependents too.
+fn resolve(subst: &Subst, sp!(loc, b_): BaseType, deps: &mut Vec<TVar>) -> Result<BaseType, Loc> {
+    let tvar = match b_ {
+        BaseType_::Var(tvar) => tvar,
+        b_ => return Ok(sp(loc, b_)),
+    };
+    if let Some(cached) = subst.unfolded.borrow().get(&tvar) {
+        deps.push(tvar);
+        return Ok(cached.clone());
+    }
+    let mut seen = vec![tvar];
+    let mut cur = tvar;
+    let bound = loop {
+        match subst.bindings.get(&cur) {
+            None => break sp(loc, BaseType_::Anything),
+            Some(sp!(_, BaseType_::Var(next))) => {
+                if seen.contains(next) {
+                    return Err(loc);
+                }
+                seen.push(*next);
+                cur = *next;
+            }
+            Some(bound) => break bound.clone(),
+        }
+    };
+    // Catch a compound self-reference (`T = Vector<T>`) here, before it's cached -- same
+    // unification bug as the direct `Var` alias loop above, just structural instead of literal.
+    if seen
+        .iter()
+        .any(|v| occurs(subst, *v, &bound, &mut HashSet::new()))
+    {
+        return Err(loc);
+    }
+    let mut own_deps = seen;
+    let result = match bound.value {
+        BaseType_::Apply(kind, tn, args) => {
+            let args = args
+                .into_iter()
+                .map(|a| resolve(subst, a, &mut own_deps))
+                .collect::<Result<Vec<_>, _>>()?;
+            sp(bound.loc, BaseType_::Apply(kind, tn, args))
+        }
+        b_ => sp(bound.loc, b_),
+    };
+    subst.unfolded.borrow_mut().insert(tvar, result.clone());
+    let mut unfolded_deps = subst.unfolded_deps.borrow_mut();
+    for dep in &own_deps {
+        unfolded_deps.entry(*dep).or_default().push(tvar);
+    }
+    drop(unfolded_deps);
+    deps.extend(own_deps);
+    Ok(result)
+}
+
+// Whether `target` occurs inside `bt`, resolving nested `Var`s through `subst` so a cycle split
+// across several tvars (`T = Vector<S>, S = Vector<T>`) is caught too, not just a direct one.
+// `visiting` guards against looping on some other, unrelated cycle encountered along the way.
+fn occurs(subst: &Subst, target: TVar, bt: &BaseType, visiting: &mut HashSet<TVar>) -> bool {
+    match &bt.value {
+        BaseType_::Var(v) if *v == target => true,
+        BaseType_::Var(v) => {
+            if !visiting.insert(*v) {
+                return false;
+            }
+            let found = match subst.bindings.get(v) {
+                Some(bound) => occurs(subst, target, bound, visiting),
+                None => false,
+            };
+            visiting.remove(v);
+            found
+        }
+        BaseType_::Apply(_, _, args) => args.iter().any(|a| occurs(subst, target, a, visiting)),
+        BaseType_::Param(_) | BaseType_::Anything => false,
+    }
+}
+
+//**************************************************************************************************
+// TVar provenance
+//**************************************************************************************************
+
+// Where a tvar was introduced, so a later "could not infer this type" diagnostic can point at
+// the origin instead of just the use site expansion noticed it unresolved at. See the UNWIRED
+// note on `Context::record_tvar_origin`: nothing currently produces one of these in a real compile.
+#[derive(Clone, Debug)]
+pub enum TVarOrigin {
+    // A generic function/struct's type parameter that nothing constrained it to.
+    Instantiation { loc: Loc, param_name: String },
+    // Any other tvar, e.g. an unannotated let-binding's declared type.
+    Local(Loc),
+}
+
+pub fn infer_kind_base(_context: &Context, _subst: &Subst, bt: BaseType) -> Result<Kind, String> {
+    use BaseType_ as B;
+    let sp!(loc, b_) = bt;
+    let kind = match b_ {
+        B::Apply(Some(kind), _, _) => kind,
+        B::Apply(None, _, _) => sp(loc, Kind_::Unrestricted),
+        B::Param(TParam { kind, .. }) => kind,
+        B::Anything => sp(loc, Kind_::Unrestricted),
+        B::Var(_) => panic!("ICE unexpanded type"),
+    };
+    Ok(kind)
+}
+
+//**************************************************************************************************
+// Diagnostics
+//**************************************************************************************************
+
+// A warning doesn't fail the compilation; an error does. Ordered low-to-high so `Ord` sorts
+// diagnostics of a batch the way a renderer would want them grouped.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+// codespan-reporting's distinction between a diagnostic's one primary span (the thing that's
+// actually wrong) and its secondary spans (context/notes pointing elsewhere). `Diagnostic` keeps
+// that distinction instead of a flat label list so a renderer can group and underline them
+// differently, the way `codespan_reporting::diagnostic::Diagnostic`'s `Label`s do.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum LabelStyle {
+    Primary,
+    Secondary,
+}
+
+#[derive(Clone, Debug)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub labels: Vec<(LabelStyle, Loc, String)>,
+}
+
+impl Diagnostic {
+    // A pragmatic stand-in for a `codespan_reporting::term::emit`-style renderer: it groups by
+    // the same primary/secondary structure, but prints each label's location instead of pulling
+    // the matching line out of a source file and underlining it. Full snippet-with-underlines
+    // rendering needs the compiler's file/source map, which doesn't live in this slice of
+    // `typing::core` -- once it does, swapping this for `codespan_reporting::term::emit` is a
+    // matter of mapping `Severity`/`LabelStyle` onto its types, not restructuring `Diagnostic`.
+    pub fn render(&self) -> String {
+        let tag = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        let mut out = String::new();
+        for (style, loc, msg) in &self.labels {
+            match style {
+                LabelStyle::Primary => out.push_str(&format!("{}: {}\n  --> {:?}\n", tag, msg, loc)),
+                LabelStyle::Secondary => out.push_str(&format!("  = note: {}\n    --> {:?}\n", msg, loc)),
+            }
+        }
+        out
+    }
+}
+
+//**************************************************************************************************
+// Context
+//**************************************************************************************************
+
+pub struct Context {
+    pub subst: Subst,
+    diags: Vec<Diagnostic>,
+    tvar_origins: HashMap<TVar, TVarOrigin>,
+}
+
+impl Context {
+    pub fn new() -> Self {
+        Self {
+            subst: Subst::empty(),
+            diags: vec![],
+            tvar_origins: HashMap::new(),
+        }
+    }
+
+    // UNWIRED: records where `tvar` was introduced, but nothing in this crate slice calls it.
+    // Tvars are allocated during unification/instantiation, and neither lives in this slice, so
+    // real compiles never populate `tvar_origins` -- every unconstrained-tvar diagnostic still
+    // gets just the single generic label it got before this existed. Exercised only by unit
+    // tests (below and in `typing::expand`), which record an origin by hand. Do not land this as "done" until something
+    // outside this file actually calls it.
+    pub fn record_tvar_origin(&mut self, tvar: TVar, origin: TVarOrigin) {
+        self.tvar_origins.insert(tvar, origin);
+    }
+
+    pub fn tvar_origin(&self, tvar: TVar) -> Option<&TVarOrigin> {
+        self.tvar_origins.get(&tvar)
+    }
+
+    // Binds `tvar` to `bt` in the substitution so every occurrence of `tvar` -- not just the
+    // `Type`/`BaseType` AST node currently in hand -- resolves to `bt` from here on.
+    pub fn bind_tvar(&mut self, tvar: TVar, bt: BaseType) {
+        self.subst.bind(tvar, bt)
+    }
+
+    // Callers pass labels in the order they matter: the first is the diagnostic's primary span,
+    // any after it are secondary context. That's already how every existing call site orders its
+    // labels (the "what's wrong" message first, "here's why" notes after), so this just tags that
+    // existing convention with `LabelStyle` instead of asking callers to tag it themselves.
+    pub fn diag(&mut self, severity: Severity, labels: Vec<(Loc, String)>) {
+        let labels = labels
+            .into_iter()
+            .enumerate()
+            .map(|(i, (loc, msg))| {
+                let style = if i == 0 {
+                    LabelStyle::Primary
+                } else {
+                    LabelStyle::Secondary
+                };
+                (style, loc, msg)
+            })
+            .collect();
+        self.diags.push(Diagnostic { severity, labels });
+    }
+
+    pub fn error(&mut self, labels: Vec<(Loc, String)>) {
+        self.diag(Severity::Error, labels)
+    }
+
+    pub fn warning(&mut self, labels: Vec<(Loc, String)>) {
+        self.diag(Severity::Warning, labels)
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.diags
+            .iter()
+            .any(|d| d.severity == Severity::Error)
+    }
+
+    pub fn diags(&self) -> &[Diagnostic] {
+        &self.diags
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use move_ir_types::location::FileHash;
+
+    fn loc() -> Loc {
+        Loc::new(FileHash::empty(), 0, 0)
+    }
+
+    // A warning-only diagnostic must never flip `has_errors()` -- that's the whole point of
+    // having a `Severity` instead of a single untyped diagnostic bucket (added to reclassify
+    // unused-locals from an error to a warning without also silencing real errors).
+    #[test]
+    fn warnings_do_not_count_as_errors() {
+        let mut context = Context::new();
+        context.warning(vec![(loc(), "just a warning".to_owned())]);
+        assert!(!context.has_errors());
+
+        context.error(vec![(loc(), "now a real error".to_owned())]);
+        assert!(context.has_errors());
+    }
+
+    // Binding one tvar must only invalidate memoized `unfold_type_base` entries whose chain
+    // actually passed through it, not an unrelated tvar's already-resolved entry.
+    #[test]
+    fn bind_only_invalidates_dependent_cache_entries() {
+        let mut subst = Subst::empty();
+        let unrelated = TVar(0);
+        let chained = TVar(1);
+        let target = TVar(2);
+
+        subst.bind(unrelated, sp(loc(), BaseType_::Anything));
+        subst.bind(chained, sp(loc(), BaseType_::Var(target)));
+
+        // warm both cache entries
+        let unrelated_before = unfold_type_base(&subst, sp(loc(), BaseType_::Var(unrelated))).unwrap();
+        let _ = unfold_type_base(&subst, sp(loc(), BaseType_::Var(chained))).unwrap();
+        assert!(subst.unfolded.borrow().contains_key(&unrelated));
+        assert!(subst.unfolded.borrow().contains_key(&chained));
+
+        // binding `target` (which `chained` chains through) must drop `chained`'s entry but
+        // leave `unrelated`'s alone
+        subst.bind(target, sp(loc(), BaseType_::Anything));
+        assert!(subst.unfolded.borrow().contains_key(&unrelated));
+        assert!(!subst.unfolded.borrow().contains_key(&chained));
+
+        let unrelated_after = unfold_type_base(&subst, sp(loc(), BaseType_::Var(unrelated))).unwrap();
+        assert_eq!(format!("{:?}", unrelated_before), format!("{:?}", unrelated_after));
+    }
+
+    // A tvar bound back to itself through a chain of other tvars must not infinite-loop or
+    // abort the process -- the occurs-check reports it as a recoverable `Err`, not a panic.
+    #[test]
+    fn cyclic_substitution_is_reported_not_panicked() {
+        let mut subst = Subst::empty();
+        let a = TVar(0);
+        let b = TVar(1);
+        subst.bind(a, sp(loc(), BaseType_::Var(b)));
+        subst.bind(b, sp(loc(), BaseType_::Var(a)));
+
+        match unfold_type_base(&subst, sp(loc(), BaseType_::Var(a))) {
+            Err(cycle_loc) => assert_eq!(format!("{:?}", cycle_loc), format!("{:?}", loc())),
+            Ok(bt) => panic!("expected a cyclic-substitution error, got {:?}", bt),
+        }
+    }
+
+    // A compound type that embeds the very tvar it's bound to (e.g. `T = Vector<T>`) is just as
+    // much a cyclic substitution as a direct `Var` alias loop, and must be reported the same way
+    // rather than being cached and reified into unbounded recursion. Reuses `u64`'s `Apply` shell
+    // (any `BaseType_::Apply` will do -- this test only cares about the recursive structure, not
+    // what type it nominally is) to avoid hand-rolling a `TypeName_`.
+    #[test]
+    fn cyclic_substitution_through_a_compound_type_is_reported_not_looped() {
+        use crate::naming::ast::Type_;
+
+        let t = TVar(0);
+        let self_referential = match Type_::u64(loc()).value {
+            Type_::Single(sp!(_, crate::naming::ast::SingleType_::Base(sp!(
+                apply_loc,
+                BaseType_::Apply(kind, tn, _)
+            )))) => sp(apply_loc, BaseType_::Apply(kind, tn, vec![sp(loc(), BaseType_::Var(t))])),
+            other => panic!("expected Type_::u64 to produce an Apply base type, got {:?}", other),
+        };
+
+        let mut subst = Subst::empty();
+        subst.bind(t, self_referential);
+
+        match unfold_type_base(&subst, sp(loc(), BaseType_::Var(t))) {
+            Err(cycle_loc) => assert_eq!(format!("{:?}", cycle_loc), format!("{:?}", loc())),
+            Ok(bt) => panic!("expected a cyclic-substitution error, got {:?}", bt),
+        }
+    }
+
+    // `Context::tvar_origin` must return exactly what was last recorded for a given tvar, and
+    // `None` for one nothing ever recorded an origin for.
+    #[test]
+    fn tvar_origin_round_trips_through_context() {
+        let mut context = Context::new();
+        let recorded = TVar(0);
+        let unrecorded = TVar(1);
+        context.record_tvar_origin(recorded, TVarOrigin::Local(loc()));
+
+        assert!(context.tvar_origin(unrecorded).is_none());
+        match context.tvar_origin(recorded) {
+            Some(TVarOrigin::Local(l)) => assert_eq!(format!("{:?}", l), format!("{:?}", loc())),
+            other => panic!("expected a recorded Local origin, got {:?}", other),
+        }
+    }
+
+    // `Context::diag` tags the first label primary and the rest secondary; callers shouldn't
+    // have to do that tagging themselves.
+    #[test]
+    fn first_label_is_primary_rest_are_secondary() {
+        let mut context = Context::new();
+        context.error(vec![
+            (loc(), "primary".to_owned()),
+            (loc(), "secondary one".to_owned()),
+            (loc(), "secondary two".to_owned()),
+        ]);
+        let diag = &context.diags()[0];
+        assert_eq!(diag.labels[0].0, LabelStyle::Primary);
+        assert_eq!(diag.labels[1].0, LabelStyle::Secondary);
+        assert_eq!(diag.labels[2].0, LabelStyle::Secondary);
+    }
+}
@@ -90,20 +90,27 @@ pub fn base_type(context: &mut Context, bt: &mut BaseType) -> bool {
     use BaseType_ as B;
     match &mut bt.value {
         B::Var(tvar) => {
+            // `unfold_type_base` memoizes the fully reified result per tvar, nested arguments
+            // included, so a repeated occurrence is an O(1) cache hit, not a re-unfold. A cyclic
+            // substitution is caught there and reported rather than looped or recursed on.
             let btvar = sp(bt.loc, B::Var(*tvar));
-            let replacement = core::unfold_type_base(&context.subst, btvar);
-            match &replacement {
-                sp!(_, B::Var(_)) => panic!("ICE unfold_type_base failed to expand"),
-                sp!(_, B::Anything) => {
-                    context.error(
-                        // TODO maybe try to point to which type parameter this tvar is for
-                        vec![(
-                            bt.loc,
-                            "Could not infer this type. Try adding an annotation",
-                        )],
-                    )
+            let replacement = match core::unfold_type_base(&context.subst, btvar) {
+                Ok(replacement) => {
+                    if let sp!(_, B::Anything) = &replacement {
+                        report_unconstrained_tvar(context, *tvar, bt.loc);
+                    }
+                    replacement
                 }
-                _ => (),
+                Err(cycle_loc) => {
+                    context.error(vec![(
+                        cycle_loc,
+                        "Cyclic substitution detected while resolving this type".to_owned(),
+                    )]);
+                    sp(bt.loc, B::Anything)
+                }
+            };
+            if let sp!(_, B::Var(_)) = &replacement {
+                panic!("ICE unfold_type_base failed to expand");
             }
             *bt = replacement;
             base_type(context, bt);
@@ -130,6 +137,76 @@ pub fn base_type(context: &mut Context, bt: &mut BaseType) -> bool {
     }
 }
 
+// The largest value that fits in `bt` without overflowing, used to decide whether an inferred
+// numeric literal is too large for the type it resolved to (e.g. `256` where a `u8` is expected).
+// Pulled out as its own function so the overflow boundary for each builtin width is
+// independently testable.
+fn numeric_literal_max(bt: BuiltinTypeName_) -> u128 {
+    use BuiltinTypeName_ as BT;
+    match bt {
+        BT::U8 => std::u8::MAX as u128,
+        BT::U64 => std::u64::MAX as u128,
+        BT::U128 => std::u128::MAX,
+        _ => unreachable!(),
+    }
+}
+
+// If an `InferredNum` literal's type variable is still unconstrained (`Anything`) once we
+// reach expansion, default it to `u64` rather than reporting "could not infer this type". The
+// default is written back into `context.subst`, not just this `Type` node, since the same tvar
+// is likely shared with other occurrences that need to see it too.
+fn default_numeric_literal_type(context: &mut Context, ty: &mut Type) {
+    if let Type_::Single(sp!(_, SingleType_::Base(sp!(loc, BaseType_::Var(tvar))))) = &ty.value {
+        let (loc, tvar) = (*loc, *tvar);
+        let unfolded = match core::unfold_type_base(&context.subst, sp(loc, BaseType_::Var(tvar))) {
+            Ok(unfolded) => unfolded,
+            Err(cycle_loc) => {
+                context.error(vec![(
+                    cycle_loc,
+                    "Cyclic substitution detected while resolving this type".to_owned(),
+                )]);
+                // The cycle is already reported; fall back to `u64` as for an unconstrained
+                // literal, since `Anything` (like an unresolved `Var`) has no numeric builtin
+                // name and `exp`'s `InferredNum` arm would panic on it.
+                *ty = Type_::u64(ty.loc);
+                return;
+            }
+        };
+        if let sp!(_, BaseType_::Anything) = &unfolded {
+            let u64_ty = Type_::u64(loc);
+            if let Type_::Single(sp!(_, SingleType_::Base(u64_bt))) = u64_ty.value {
+                context.bind_tvar(tvar, u64_bt);
+            }
+            *ty = Type_::u64(ty.loc);
+            return;
+        }
+    }
+    type_(context, ty)
+}
+
+// Adds a provenance label when `context.tvar_origin(tvar)` has one. In a real compile this is
+// currently always `None` -- see the UNWIRED note on `Context::record_tvar_origin` -- so this
+// still only ever emits the single generic label it always has.
+fn report_unconstrained_tvar(context: &mut Context, tvar: core::TVar, use_loc: Loc) {
+    let mut labels = vec![(
+        use_loc,
+        "Could not infer this type. Try adding an annotation".to_owned(),
+    )];
+    if let Some(origin) = context.tvar_origin(tvar) {
+        labels.push(match origin {
+            core::TVarOrigin::Instantiation { loc, param_name } => (
+                *loc,
+                format!(
+                    "'{}' is never constrained by how it's used here",
+                    param_name
+                ),
+            ),
+            core::TVarOrigin::Local(loc) => (*loc, "declared without a type here".to_owned()),
+        });
+    }
+    context.error(labels)
+}
+
 fn get_kind(s: &SingleType) -> Kind {
     use SingleType_ as S;
     match &s.value {
@@ -149,6 +226,36 @@ fn get_kind_base(b: &BaseType) -> Kind {
     }
 }
 
+// An unrestricted vector is expensive to copy, unlike an unrestricted scalar. A struct isn't
+// flagged: this only sees its type, not its field list, so "expensive" can't be judged here.
+fn is_expensive_to_copy(s: &SingleType) -> bool {
+    use BaseType_ as B;
+    use SingleType_ as S;
+    use crate::naming::ast::TypeName_ as TN;
+    match &s.value {
+        S::Ref(_, _) => false,
+        S::Base(sp!(_, B::Apply(_, sp!(_, TN::Builtin(sp!(_, BuiltinTypeName_::Vector(_)))), _))) => {
+            true
+        }
+        S::Base(_) => false,
+    }
+}
+
+// The "Implicit copy" warning for an `E::Use` of an unrestricted-but-expensive-to-copy local, or
+// `None` if this use doesn't warrant one.
+fn implicit_copy_warning(kind: &Kind, st: &SingleType, var_display: &str) -> Option<String> {
+    if let Kind_::Unrestricted = &kind.value {
+        if is_expensive_to_copy(st) {
+            return Some(format!(
+                "Implicit copy of '{}'. This value's type is unrestricted but may be expensive \
+                 to duplicate; consider an explicit 'copy' here",
+                var_display
+            ));
+        }
+    }
+    None
+}
+
 //**************************************************************************************************
 // Expressions
 //**************************************************************************************************
@@ -166,9 +273,13 @@ fn sequence_item(context: &mut Context, item: &mut T::SequenceItem) {
 
         S::Declare(tbind) => bind_list(context, tbind),
         S::Bind(tbind, tys, te) => {
+            // `te`'s type may share a tvar with `tbind`'s declared type (e.g. `let x = 0;`
+            // constrains `x`'s tvar to the literal's). Expand `te` first so a still-unconstrained
+            // numeric literal gets defaulted in `context.subst` before `bind_list` looks the same
+            // tvar up and would otherwise report it as uninferred.
+            exp(context, te);
             bind_list(context, tbind);
             expected_types(context, tys);
-            exp(context, te)
         }
     }
 }
@@ -185,6 +296,9 @@ fn exp(context: &mut Context, e: &mut T::Exp) {
                 *t = Type_::anything(t.loc);
             }
         },
+        // an inferred numeric literal that never got constrained defaults to u64 rather than
+        // going through the normal (error-on-`Anything`) type expansion
+        E::InferredNum(_) => default_numeric_literal_type(context, &mut e.ty),
         _ => type_(context, &mut e.ty),
     }
     match &mut e.exp.value {
@@ -197,7 +311,11 @@ fn exp(context: &mut Context, e: &mut T::Exp) {
             };
             let from_user = false;
             let var = v.clone();
-            e.exp.value = match get_kind(st).value {
+            let kind = get_kind(st);
+            if let Some(msg) = implicit_copy_warning(&kind, st, &v.to_string()) {
+                context.warning(vec![(e.exp.loc, msg)]);
+            }
+            e.exp.value = match kind.value {
                 Kind_::Unrestricted => E::Copy { from_user, var },
                 Kind_::Unknown | Kind_::Affine | Kind_::Resource => E::Move { from_user, var },
             }
@@ -209,24 +327,16 @@ fn exp(context: &mut Context, e: &mut T::Exp) {
                 _ => panic!("ICE inferred num failed {:?}", &e.ty.value),
             };
             let v = *v;
-            let u8_max = std::u8::MAX as u128;
-            let u64_max = std::u64::MAX as u128;
-            let u128_max = std::u128::MAX;
-            let max = match bt {
-                BT::U8 => u8_max,
-                BT::U64 => u64_max,
-                BT::U128 => u128_max,
-                _ => unreachable!(),
-            };
+            let max = numeric_literal_max(bt);
             let new_exp = if v > max {
                 let msg = format!(
                     "Expected a literal of type '{}', but the value is too large.",
                     bt
                 );
-                let fix_bt = if v > u64_max {
+                let fix_bt = if v > numeric_literal_max(BT::U64) {
                     BT::U128
                 } else {
-                    assert!(v > u8_max);
+                    assert!(v > numeric_literal_max(BT::U8));
                     BT::U64
                 };
                 let fix = format!(
@@ -312,6 +422,11 @@ fn exp(context: &mut Context, e: &mut T::Exp) {
             exp(context, el);
             type_(context, rhs_ty);
         }
+        // TODO: tuple element projection (`t.0`) is NOT implemented by this backlog item. It
+        // needs a new `T::UnannotatedExp_` variant, parser grammar, and a `typing::translate`
+        // producer, none of which exist anywhere in this crate slice -- `typing::expand` alone
+        // has nothing to add an arm for. Unimplemented, not a scoped-out decision; picking this
+        // up requires touching ast.rs/parser/translate.rs, outside what this series has changed.
     }
 }
 
@@ -326,11 +441,7 @@ fn bind(context: &mut Context, b: &mut T::Bind) {
     match &mut b.value {
         B::Ignore => (),
         B::Var(v, None) => {
-            let msg = format!(
-                "Unused local '{0}'. Consider removing or prefixing with an underscore: '_{0}'",
-                v
-            );
-            context.error(vec![(b.loc, msg)]);
+            context.warning(vec![(b.loc, unused_local_warning(&v.to_string()))]);
             b.value = B::Ignore
         }
         B::Var(_, Some(st)) => {
@@ -346,6 +457,14 @@ fn bind(context: &mut Context, b: &mut T::Bind) {
     }
 }
 
+// The "Unused local" warning for a binding with no subsequent use.
+fn unused_local_warning(var_display: &str) -> String {
+    format!(
+        "Unused local '{0}'. Consider removing or prefixing with an underscore: '_{0}'",
+        var_display
+    )
+}
+
 fn assign_list(context: &mut Context, assigns: &mut T::AssignList) {
     for a in &mut assigns.value {
         assign(context, a)
@@ -407,3 +526,161 @@ fn exp_list_item(context: &mut Context, item: &mut T::ExpListItem) {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use move_ir_types::location::FileHash;
+
+    fn loc() -> Loc {
+        Loc::new(FileHash::empty(), 0, 0)
+    }
+
+    fn u64_base_type() -> BaseType {
+        match Type_::u64(loc()).value {
+            Type_::Single(sp!(_, SingleType_::Base(bt))) => bt,
+            _ => unreachable!(),
+        }
+    }
+
+    // An `InferredNum` literal whose tvar never got constrained by unification defaults to
+    // `u64` rather than reporting "could not infer this type" -- and the default is bound into
+    // `context.subst`, not just rewritten onto this one `Type` node, so any other occurrence of
+    // the same tvar sees it too.
+    #[test]
+    fn unconstrained_tvar_defaults_to_u64_and_binds_into_subst() {
+        let mut context = Context::new();
+        let tvar = core::TVar(0);
+        let mut ty = sp(
+            loc(),
+            Type_::Single(sp(loc(), SingleType_::Base(sp(loc(), BaseType_::Var(tvar))))),
+        );
+
+        default_numeric_literal_type(&mut context, &mut ty);
+
+        match &ty.value {
+            Type_::Single(sp!(_, SingleType_::Base(bt))) => {
+                assert_eq!(format!("{:?}", bt), format!("{:?}", u64_base_type()))
+            }
+            other => panic!("expected a single base type, got {:?}", other),
+        }
+
+        let resolved = core::unfold_type_base(&context.subst, sp(loc(), BaseType_::Var(tvar)))
+            .expect("not a cyclic substitution");
+        assert_eq!(format!("{:?}", resolved), format!("{:?}", u64_base_type()));
+    }
+
+    // A type that isn't an unconstrained tvar (e.g. a bare `Unit`, as in a statement with no
+    // value) isn't a numeric-literal defaulting candidate at all, so it must fall through to
+    // ordinary expansion untouched rather than being coerced to `u64`.
+    #[test]
+    fn non_tvar_type_is_left_for_ordinary_expansion() {
+        let mut context = Context::new();
+        let mut ty = sp(loc(), Type_::Unit);
+
+        default_numeric_literal_type(&mut context, &mut ty);
+
+        match &ty.value {
+            Type_::Unit => (),
+            other => panic!("expected Unit to be left untouched, got {:?}", other),
+        }
+    }
+
+    // Once something has recorded the tvar's origin, the "could not infer this type" diagnostic
+    // gets a second, origin-pointing label; with no recorded origin it stays the single-label
+    // diagnostic it's always been.
+    #[test]
+    fn unconstrained_tvar_report_adds_origin_label_when_recorded() {
+        let mut context = Context::new();
+        let tvar = core::TVar(0);
+        context.record_tvar_origin(
+            tvar,
+            core::TVarOrigin::Instantiation {
+                loc: loc(),
+                param_name: "T".to_owned(),
+            },
+        );
+
+        report_unconstrained_tvar(&mut context, tvar, loc());
+
+        assert_eq!(context.diags()[0].labels.len(), 2);
+    }
+
+    #[test]
+    fn unconstrained_tvar_report_has_one_label_without_an_origin() {
+        let mut context = Context::new();
+
+        report_unconstrained_tvar(&mut context, core::TVar(0), loc());
+
+        assert_eq!(context.diags()[0].labels.len(), 1);
+    }
+
+    // boundary values for the overflow check `numeric_literal_max` backs: each width's max is
+    // exactly that type's own `MAX` constant.
+    #[test]
+    fn numeric_literal_max_matches_each_width() {
+        assert_eq!(numeric_literal_max(BuiltinTypeName_::U8), std::u8::MAX as u128);
+        assert_eq!(numeric_literal_max(BuiltinTypeName_::U64), std::u64::MAX as u128);
+        assert_eq!(numeric_literal_max(BuiltinTypeName_::U128), std::u128::MAX);
+    }
+
+    fn unrestricted_kind() -> Kind {
+        sp(loc(), Kind_::Unrestricted)
+    }
+
+    fn vector_single_type() -> SingleType {
+        use crate::naming::ast::TypeName_ as TN;
+        let elem = sp(loc(), SingleType_::Base(u64_base_type()));
+        sp(
+            loc(),
+            SingleType_::Base(sp(
+                loc(),
+                BaseType_::Apply(
+                    Some(unrestricted_kind()),
+                    sp(
+                        loc(),
+                        TN::Builtin(sp(loc(), BuiltinTypeName_::Vector(Box::new(elem)))),
+                    ),
+                    vec![],
+                ),
+            )),
+        )
+    }
+
+    // The unused-local message, reported through `Context::warning` the way `bind` reports it,
+    // is a warning, not an error, so it doesn't fail the compilation the way a type error would.
+    // `bind` itself isn't run here.
+    #[test]
+    fn unused_local_is_reported_as_a_warning_not_an_error() {
+        let mut context = Context::new();
+
+        context.warning(vec![(loc(), unused_local_warning("x"))]);
+
+        assert_eq!(context.diags()[0].severity, core::Severity::Warning);
+    }
+
+    // An unrestricted vector local warrants the "Implicit copy" message, and reported through
+    // `Context::warning` the way `exp`'s `E::Use` arm reports it, it is a warning, not an error.
+    // `exp` itself isn't run here.
+    #[test]
+    fn implicit_copy_of_an_unrestricted_vector_warns() {
+        let kind = unrestricted_kind();
+        let st = vector_single_type();
+
+        let msg = implicit_copy_warning(&kind, &st, "v").expect("expected a warning message");
+
+        let mut context = Context::new();
+        context.warning(vec![(loc(), msg)]);
+        assert_eq!(context.diags()[0].severity, core::Severity::Warning);
+    }
+
+    // An unrestricted non-vector value (e.g. `u64`) is cheap to copy, so using it doesn't warrant
+    // the warning at all.
+    #[test]
+    fn implicit_copy_of_an_unrestricted_scalar_does_not_warn() {
+        let kind = unrestricted_kind();
+        let st = sp(loc(), SingleType_::Base(u64_base_type()));
+
+        assert!(implicit_copy_warning(&kind, &st, "x").is_none());
+    }
+}